@@ -28,6 +28,22 @@
 //! The above example will print `fn:main cost 10ms` at the end, You can also use the second
 //! argument to define the format string you need.
 //!
+//! `async fn` is supported too, in which case the elapsed time spans the whole `.await`,
+//! suspension points included.
+//!
+//! The attribute can also be placed on an entire `impl` block, to time every method in it
+//! at once, leaving associated consts and types untouched:
+//! ```rust
+//! use calculagraph::timer_log_debug;
+//!
+//! struct Service;
+//!
+//! #[timer_log_debug(us)]
+//! impl Service {
+//!     fn handle(&self) {}
+//! }
+//! ```
+//!
 //! ## Note
 //! This macro added two variables that would never conflict in ordinary business, they are
 //! `now_x7bf707c839bc2554fa3f1913a8dc699b68236726c5da18b31f660948ca7f542a267de9b` and
@@ -39,8 +55,8 @@ use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{format_ident, quote};
 use syn::{
-    parse_macro_input, spanned::Spanned, Attribute, AttributeArgs, Block, Error, Item, ItemFn, Lit,
-    Meta, NestedMeta, Path, Result, Signature, Visibility,
+    parse_macro_input, spanned::Spanned, AttributeArgs, Block, Error, ImplItem, Item, ItemFn, Lit,
+    LitInt, Meta, NestedMeta, Path, Result,
 };
 
 // A suffix with a very low probability of conflict,
@@ -50,10 +66,18 @@ const UID_SUFFIX: &str = "x7bf707c839bc2554fa3f1913a8dc699b68236726c5da18b31f660
 
 /// `std::println!` the execution time after the function is called and executed.
 ///
-/// The macro support none, 1 or 2 parameters, [(`TimeUnit`[, `FormatString`])].
-/// The parameter `TimeUnit` supports four types of `s`, `ms`(by default), `us` and `ns`,
-/// The parameter `FormatString` is similar to the format string parameter in `println!`, note that
-/// only one placeholder is supported here, which will fill the time result.
+/// The macro support none, 1, 2 or 3 parameters, [(`TimeUnit`[, `FormatString`][, `min = Threshold`])].
+/// The parameter `TimeUnit` supports five types of `s`, `ms`(by default), `us`, `ns` and `auto`,
+/// The parameter `FormatString` is similar to the format string parameter in `println!`; it
+/// either contains a single `{}` placeholder that is filled with the time result (`{:?}` with
+/// `auto`, whose time result is a `Duration` rather than an integer), or one or more
+/// of the named components `[duration]`, `[unit]`, `[fn]`, `[thread]`, e.g.
+/// `"[fn] on [thread] took [duration][unit]"` (use `[[`/`]]` for literal brackets).
+/// The optional `min = Threshold` parameter only emits the output when `elapsed()`,
+/// measured in `TimeUnit`, is at least `Threshold`, e.g. `min = 50` with `ms` logs only
+/// calls that took 50ms or more; with `auto`, `Threshold` is interpreted in milliseconds.
+///
+/// Can also annotate an entire `impl` block to time every method in it.
 /// ### Examples
 /// ```
 /// #[timer_println]
@@ -78,10 +102,18 @@ pub fn timer_println(attr: TokenStream, input: TokenStream) -> TokenStream {
 
 /// `log::info!` the execution time after the function is called and executed,
 ///
-/// The macro support none, 1 or 2 parameters, [(`TimeUnit`[, `FormatString`])].
-/// The parameter `TimeUnit` supports four types of `s`, `ms`(by default), `us` and `ns`,
-/// The parameter `FormatString` is similar to the format string parameter in `println!`, note that
-/// only one placeholder is supported here, which will fill the time result.
+/// The macro support none, 1, 2 or 3 parameters, [(`TimeUnit`[, `FormatString`][, `min = Threshold`])].
+/// The parameter `TimeUnit` supports five types of `s`, `ms`(by default), `us`, `ns` and `auto`,
+/// The parameter `FormatString` is similar to the format string parameter in `println!`; it
+/// either contains a single `{}` placeholder that is filled with the time result (`{:?}` with
+/// `auto`, whose time result is a `Duration` rather than an integer), or one or more
+/// of the named components `[duration]`, `[unit]`, `[fn]`, `[thread]`, e.g.
+/// `"[fn] on [thread] took [duration][unit]"` (use `[[`/`]]` for literal brackets).
+/// The optional `min = Threshold` parameter only emits the output when `elapsed()`,
+/// measured in `TimeUnit`, is at least `Threshold`, e.g. `min = 50` with `ms` logs only
+/// calls that took 50ms or more; with `auto`, `Threshold` is interpreted in milliseconds.
+///
+/// Can also annotate an entire `impl` block to time every method in it.
 /// ### Examples
 /// ```
 /// #[timer_log_info]
@@ -106,10 +138,18 @@ pub fn timer_log_info(attr: TokenStream, input: TokenStream) -> TokenStream {
 
 /// `log::debug!` the execution time after the function is called and executed,
 ///
-/// The macro support none, 1 or 2 parameters, [(`TimeUnit`[, `FormatString`])].
-/// The parameter `TimeUnit` supports four types of `s`, `ms`(by default), `us` and `ns`,
-/// The parameter `FormatString` is similar to the format string parameter in `println!`, note that
-/// only one placeholder is supported here, which will fill the time result.
+/// The macro support none, 1, 2 or 3 parameters, [(`TimeUnit`[, `FormatString`][, `min = Threshold`])].
+/// The parameter `TimeUnit` supports five types of `s`, `ms`(by default), `us`, `ns` and `auto`,
+/// The parameter `FormatString` is similar to the format string parameter in `println!`; it
+/// either contains a single `{}` placeholder that is filled with the time result (`{:?}` with
+/// `auto`, whose time result is a `Duration` rather than an integer), or one or more
+/// of the named components `[duration]`, `[unit]`, `[fn]`, `[thread]`, e.g.
+/// `"[fn] on [thread] took [duration][unit]"` (use `[[`/`]]` for literal brackets).
+/// The optional `min = Threshold` parameter only emits the output when `elapsed()`,
+/// measured in `TimeUnit`, is at least `Threshold`, e.g. `min = 50` with `ms` logs only
+/// calls that took 50ms or more; with `auto`, `Threshold` is interpreted in milliseconds.
+///
+/// Can also annotate an entire `impl` block to time every method in it.
 /// ### Examples
 /// ```
 /// #[timer_log_debug]
@@ -134,10 +174,18 @@ pub fn timer_log_debug(attr: TokenStream, input: TokenStream) -> TokenStream {
 
 /// `log::trace!` the execution time after the function is called and executed,
 ///
-/// The macro support none, 1 or 2 parameters, [(`TimeUnit`[, `FormatString`])].
-/// The parameter `TimeUnit` supports four types of `s`, `ms`(by default), `us` and `ns`,
-/// The parameter `FormatString` is similar to the format string parameter in `println!`, note that
-/// only one placeholder is supported here, which will fill the time result.
+/// The macro support none, 1, 2 or 3 parameters, [(`TimeUnit`[, `FormatString`][, `min = Threshold`])].
+/// The parameter `TimeUnit` supports five types of `s`, `ms`(by default), `us`, `ns` and `auto`,
+/// The parameter `FormatString` is similar to the format string parameter in `println!`; it
+/// either contains a single `{}` placeholder that is filled with the time result (`{:?}` with
+/// `auto`, whose time result is a `Duration` rather than an integer), or one or more
+/// of the named components `[duration]`, `[unit]`, `[fn]`, `[thread]`, e.g.
+/// `"[fn] on [thread] took [duration][unit]"` (use `[[`/`]]` for literal brackets).
+/// The optional `min = Threshold` parameter only emits the output when `elapsed()`,
+/// measured in `TimeUnit`, is at least `Threshold`, e.g. `min = 50` with `ms` logs only
+/// calls that took 50ms or more; with `auto`, `Threshold` is interpreted in milliseconds.
+///
+/// Can also annotate an entire `impl` block to time every method in it.
 /// ### Examples
 /// ```
 /// #[timer_log_trace]
@@ -166,17 +214,31 @@ enum TimeUnit {
     MS,
     US,
     NS,
+    /// Let `elapsed()`'s `Duration` pick its own unit, the same way `micro-timer` logs
+    /// `timer.elapsed()` with `{:?}` so `1.5ms`, `340µs`, `2.1s` are chosen automatically.
+    Auto,
 }
 
 impl TimeUnit {
-    fn quote(self) -> TokenStream2 {
+    /// The method call to turn a `Duration` into the value that gets passed to the
+    /// output macro, or `None` for `Auto`, where the raw `Duration` is passed through
+    /// and formatted with `{:?}` instead.
+    fn quote(self) -> Option<TokenStream2> {
         match self {
-            TimeUnit::S => quote!(as_secs()),
-            TimeUnit::MS => quote!(as_millis()),
-            TimeUnit::US => quote!(as_micros()),
-            TimeUnit::NS => quote!(as_nanos()),
+            TimeUnit::S => Some(quote!(as_secs())),
+            TimeUnit::MS => Some(quote!(as_millis())),
+            TimeUnit::US => Some(quote!(as_micros())),
+            TimeUnit::NS => Some(quote!(as_nanos())),
+            TimeUnit::Auto => None,
         }
     }
+
+    /// Whether this unit keeps `elapsed()`'s value as a `Duration`, as opposed to
+    /// converting it to an integer, which decides whether the default format string
+    /// uses `{:?}` or `{}` for the time placeholder.
+    fn is_duration(self) -> bool {
+        matches!(self, TimeUnit::Auto)
+    }
 }
 
 impl std::fmt::Display for TimeUnit {
@@ -186,6 +248,7 @@ impl std::fmt::Display for TimeUnit {
             TimeUnit::MS => write!(f, "ms"),
             TimeUnit::US => write!(f, "us"),
             TimeUnit::NS => write!(f, "ns"),
+            TimeUnit::Auto => write!(f, "auto"),
         }
     }
 }
@@ -197,80 +260,293 @@ impl std::convert::From<String> for TimeUnit {
             "MS" => TimeUnit::MS,
             "US" => TimeUnit::US,
             "NS" => TimeUnit::NS,
-            _ => panic!("Invalid unit of time, only `s`, `ms`, `us`, `ns` are supported"),
+            "AUTO" => TimeUnit::Auto,
+            _ => panic!("Invalid unit of time, only `s`, `ms`, `us`, `ns`, `auto` are supported"),
         }
     }
 }
 
+/// A single named component recognized inside `[...]` in a `FormatString`.
+#[derive(Debug, Copy, Clone)]
+enum FormatArg {
+    /// `[duration]` — the elapsed time, in whatever shape the selected `TimeUnit` yields.
+    Duration,
+    /// `[unit]` — the literal unit suffix (`s`, `ms`, `us`, `ns`, `auto`).
+    Unit,
+    /// `[fn]` — the name of the annotated function.
+    Fn,
+    /// `[thread]` — the name of the thread the function runs on, if any.
+    Thread,
+}
+
+/// The output format, either the legacy single-`{}` string or a compile-time-parsed
+/// template with named `[...]` components expanded to ordered `{}` placeholders.
+#[derive(Clone)]
+enum OutputFormat {
+    Simple(String),
+    Template { fmt: String, o_args: Vec<FormatArg> },
+}
+
 fn builder(args: &AttributeArgs, body: &Item, outputter: &TokenStream2) -> Result<TokenStream> {
-    let (f_attrs, f_vis, f_sig, f_block) = parse_body(body)?;
-    let f_name = &f_sig.ident.to_string();
-    let (t_unit, o_format) = parse_args(args, f_name)?;
+    let (t_unit, o_format, o_min) = parse_args(args)?;
 
-    return Ok((move || {
-        let t_formatter = t_unit.quote();
-        let v_now = format_ident!("now_{}", UID_SUFFIX);
-        let v_result = format_ident!("result_{}", UID_SUFFIX);
-        let f_stmts = &f_block.stmts;
-        quote!(
-            #(#f_attrs)* #f_vis #f_sig {
-                let #v_now = std::time::Instant::now();
-                let #v_result = (move ||{ #(#f_stmts)* })();
-                #outputter(#o_format, #v_now . elapsed(). #t_formatter );
-                return #v_result;
+    match body {
+        Item::Fn(item_fn) => {
+            let ItemFn {
+                attrs,
+                vis,
+                sig,
+                block,
+            } = item_fn;
+            let f_name = sig.ident.to_string();
+            let new_block = instrument_block(
+                block,
+                &f_name,
+                t_unit,
+                &o_format,
+                &o_min,
+                outputter,
+                sig.asyncness.is_some(),
+            );
+            Ok(quote!(#(#attrs)* #vis #sig #new_block).into())
+        }
+        Item::Impl(item_impl) => {
+            let ty_name = impl_type_name(&item_impl.self_ty);
+            let mut item_impl = item_impl.clone();
+            for item in item_impl.items.iter_mut() {
+                if let ImplItem::Method(method) = item {
+                    let f_name = format!("{}::{}", ty_name, method.sig.ident);
+                    let new_block = instrument_block(
+                        &method.block,
+                        &f_name,
+                        t_unit,
+                        &o_format,
+                        &o_min,
+                        outputter,
+                        method.sig.asyncness.is_some(),
+                    );
+                    method.block = syn::parse2(new_block)?;
+                }
             }
-        )
-    })()
-    .into());
+            Ok(quote!(#item_impl).into())
+        }
+        _ => Err(Error::new(
+            body.span(),
+            "Only `fn` and `impl` items are supported",
+        )),
+    }
+}
 
-    // -> (time_unit, output_format_string)
-    fn parse_args(args: &AttributeArgs, fn_name: &String) -> Result<(TimeUnit, String)> {
-        match &args[..] {
-            [] => Ok((TimeUnit::MS, format!("fn:{} cost {{}}ms", fn_name))),
-            [u] => {
-                let u = read_time_unit(u)?;
-                Ok((u, format!("fn:{} cost {{}}{}", fn_name, u)))
-            }
-            [u, f] => Ok((read_time_unit(u)?, read_output_format(f)?)),
-            _ => panic!("Invalid arguments, usage: [(TimeUnit[, OutputFormatString])]"),
+/// Wraps a function body with the `Instant::now()` / `elapsed()` timing scaffolding and
+/// the (optionally threshold-gated) output call. `fn_name` is baked into the default
+/// `OutputFormat` and into the `[fn]` format component.
+fn instrument_block(
+    block: &Block,
+    fn_name: &str,
+    t_unit: TimeUnit,
+    o_format: &Option<OutputFormat>,
+    o_min: &Option<LitInt>,
+    outputter: &TokenStream2,
+    is_async: bool,
+) -> TokenStream2 {
+    let v_now = format_ident!("now_{}", UID_SUFFIX);
+    let v_result = format_ident!("result_{}", UID_SUFFIX);
+    let f_stmts = &block.stmts;
+    let v_elapsed = match t_unit.quote() {
+        Some(t_formatter) => quote!(#v_now . elapsed(). #t_formatter),
+        None => quote!(#v_now . elapsed()),
+    };
+    let o_format = o_format
+        .clone()
+        .unwrap_or_else(|| OutputFormat::Simple(default_format(t_unit, fn_name)));
+    let v_call = match &o_format {
+        OutputFormat::Simple(fmt) => quote!(#outputter(#fmt, #v_elapsed)),
+        OutputFormat::Template { fmt, o_args } => {
+            let o_args: Vec<TokenStream2> = o_args
+                .iter()
+                .map(|a| match a {
+                    FormatArg::Duration => v_elapsed.clone(),
+                    FormatArg::Unit => {
+                        let u = t_unit.to_string();
+                        quote!(#u)
+                    }
+                    FormatArg::Fn => quote!(#fn_name),
+                    FormatArg::Thread => {
+                        quote!(std::thread::current().name().unwrap_or("unnamed").to_string())
+                    }
+                })
+                .collect();
+            quote!(#outputter(#fmt #(, #o_args)*))
+        }
+    };
+    let v_output = match o_min {
+        Some(min) if t_unit.is_duration() => {
+            quote!(if #v_elapsed >= std::time::Duration::from_millis(#min) { #v_call; })
         }
+        Some(min) => quote!(if #v_elapsed >= #min { #v_call; }),
+        None => quote!(#v_call;),
+    };
+    if is_async {
+        quote!({
+            let #v_now = std::time::Instant::now();
+            let #v_result = (async move { #(#f_stmts)* }).await;
+            #v_output
+            return #v_result;
+        })
+    } else {
+        quote!({
+            let #v_now = std::time::Instant::now();
+            let #v_result = (move ||{ #(#f_stmts)* })();
+            #v_output
+            return #v_result;
+        })
     }
+}
+
+/// The default `OutputFormat` when the user didn't supply a `FormatString`.
+fn default_format(u: TimeUnit, fn_name: &str) -> String {
+    if u.is_duration() {
+        format!("fn:{} cost {{:?}}", fn_name)
+    } else {
+        format!("fn:{} cost {{}}{}", fn_name, u)
+    }
+}
+
+/// The type name that prefixes each method's name (`[fn]` and the default format string)
+/// inside an instrumented `impl` block, e.g. `Service` in `Service::handle`.
+fn impl_type_name(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_else(|| quote!(#ty).to_string()),
+        _ => quote!(#ty).to_string(),
+    }
+}
+
+// -> (time_unit, output_format, min_threshold); `output_format` is `None` when the
+// caller didn't supply a `FormatString`, meaning the default should be derived from
+// each instrumented function's own name.
+fn parse_args(args: &AttributeArgs) -> Result<(TimeUnit, Option<OutputFormat>, Option<LitInt>)> {
+    match &args[..] {
+        [] => Ok((TimeUnit::MS, None, None)),
+        [u] => Ok((read_time_unit(u)?, None, None)),
+        [u, m] if is_min(m) => Ok((read_time_unit(u)?, None, Some(read_min(m)?))),
+        [u, f] => {
+            let u = read_time_unit(u)?;
+            Ok((u, Some(read_output_format(f, u)?), None))
+        }
+        [u, f, m] => {
+            let u = read_time_unit(u)?;
+            Ok((u, Some(read_output_format(f, u)?), Some(read_min(m)?)))
+        }
+        _ => panic!("Invalid arguments, usage: [(TimeUnit[, OutputFormatString][, min = Threshold])]"),
+    }
+}
 
-    fn parse_body(body: &Item) -> Result<(&Vec<Attribute>, &Visibility, &Signature, &Box<Block>)> {
-        match body {
-            Item::Fn(f @ _) => {
-                let ItemFn {
-                    attrs,
-                    vis,
-                    sig,
-                    block,
-                } = f;
-                Ok((attrs, vis, sig, block))
+fn is_min(m: &NestedMeta) -> bool {
+    matches!(m, NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("min"))
+}
+
+fn read_min(m: &NestedMeta) -> Result<LitInt> {
+    if let NestedMeta::Meta(Meta::NameValue(nv)) = m {
+        if nv.path.is_ident("min") {
+            if let Lit::Int(i) = &nv.lit {
+                return Ok(i.clone());
             }
-            _ => Err(Error::new(
-                body.span(),
-                "Statement other than function are not supported",
-            )),
         }
     }
+    Err(syn::Error::new(
+        m.span(),
+        "Invalid `min` argument, usage: min = <integer threshold>",
+    ))
+}
+
+fn read_time_unit(u: &NestedMeta) -> Result<TimeUnit> {
+    if let NestedMeta::Meta(Meta::Path(Path { segments, .. })) = u {
+        return Ok(segments[0].ident.to_string().into());
+    }
+    Err(syn::Error::new(
+        u.span(),
+        "Invalid argument, `TimeUnit` expected",
+    ))
+}
 
-    fn read_time_unit(u: &NestedMeta) -> Result<TimeUnit> {
-        if let NestedMeta::Meta(Meta::Path(Path { segments, .. })) = u {
-            return Ok(segments[0].ident.to_string().into());
+fn read_output_format(f: &NestedMeta, t_unit: TimeUnit) -> Result<OutputFormat> {
+    match f {
+        NestedMeta::Lit(Lit::Str(s)) => {
+            let raw = s.value();
+            if raw.contains('[') {
+                let (fmt, o_args) = parse_format_components(&raw, s.span(), t_unit)?;
+                Ok(OutputFormat::Template { fmt, o_args })
+            } else {
+                Ok(OutputFormat::Simple(raw))
+            }
         }
-        Err(syn::Error::new(
-            u.span(),
-            "Invalid argument, `TimeUnit` expected",
-        ))
+        _ => Err(syn::Error::new(
+            f.span(),
+            "Invalid FormatString, the usage is similar with macro `println!` or `format!`",
+        )),
     }
+}
 
-    fn read_output_format(f: &NestedMeta) -> Result<String> {
-        match f {
-            NestedMeta::Lit(Lit::Str(s)) => Ok(s.value()),
-            _ => Err(syn::Error::new(
-                f.span(),
-                "Invalid FormatString, the usage is similar with macro `println!` or `format!`",
-            )),
+// Lexes `[duration]`, `[unit]`, `[fn]` and `[thread]` components out of a format
+// literal, turning each into a `{}` placeholder (or `{:?}` for `[duration]` when
+// `t_unit` is `auto`, since then the bound value is a raw `Duration`) and recording,
+// in order, which value it stands for. `[[` and `]]` escape to literal `[` and `]`.
+fn parse_format_components(
+    raw: &str,
+    span: proc_macro2::Span,
+    t_unit: TimeUnit,
+) -> Result<(String, Vec<FormatArg>)> {
+    let mut fmt = String::with_capacity(raw.len());
+    let mut o_args = Vec::new();
+    let mut chars = raw.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '[' if chars.peek().map(|&(_, c)| c) == Some('[') => {
+                chars.next();
+                fmt.push('[');
+            }
+            ']' if chars.peek().map(|&(_, c)| c) == Some(']') => {
+                chars.next();
+                fmt.push(']');
+            }
+            '[' => {
+                let name: String = chars
+                    .by_ref()
+                    .take_while(|&(_, c)| c != ']')
+                    .map(|(_, c)| c)
+                    .collect();
+                let arg = match name.as_str() {
+                    "duration" => FormatArg::Duration,
+                    "unit" => FormatArg::Unit,
+                    "fn" => FormatArg::Fn,
+                    "thread" => FormatArg::Thread,
+                    _ => {
+                        return Err(syn::Error::new(
+                            span,
+                            format!(
+                                "Unknown format component `[{}]`, expected one of \
+                                 `[duration]`, `[unit]`, `[fn]`, `[thread]`",
+                                name
+                            ),
+                        ))
+                    }
+                };
+                fmt.push_str(
+                    if matches!(arg, FormatArg::Duration) && t_unit.is_duration() {
+                        "{:?}"
+                    } else {
+                        "{}"
+                    },
+                );
+                o_args.push(arg);
+            }
+            _ => fmt.push(c),
         }
     }
+    Ok((fmt, o_args))
 }
@@ -0,0 +1,73 @@
+//! Covers the sync/async x with/without return value matrix for the
+//! `timer_*` macros, since `#[timer_println]` rewrites the function body
+//! differently depending on `async fn` vs `fn`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::{thread, time};
+
+use calculagraph::timer_println;
+
+fn noop_raw_waker() -> RawWaker {
+    fn no_op(_: *const ()) {}
+    fn clone(_: *const ()) -> RawWaker {
+        noop_raw_waker()
+    }
+    let vtable = &RawWakerVTable::new(clone, no_op, no_op, no_op);
+    RawWaker::new(std::ptr::null(), vtable)
+}
+
+// The functions under test never actually suspend, so a single poll always
+// drives them to completion; this avoids pulling in an async runtime dependency.
+fn block_on<F: Future>(mut fut: F) -> F::Output {
+    let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = unsafe { Pin::new_unchecked(&mut fut) };
+    match fut.as_mut().poll(&mut cx) {
+        Poll::Ready(v) => v,
+        Poll::Pending => panic!("future did not complete on first poll"),
+    }
+}
+
+#[timer_println(ms)]
+fn sync_no_return() {
+    thread::sleep(time::Duration::from_millis(1));
+}
+
+#[timer_println(ms)]
+fn sync_with_return() -> u32 {
+    thread::sleep(time::Duration::from_millis(1));
+    42
+}
+
+#[timer_println(ms)]
+async fn async_no_return() {
+    thread::sleep(time::Duration::from_millis(1));
+}
+
+#[timer_println(ms)]
+async fn async_with_return() -> u32 {
+    thread::sleep(time::Duration::from_millis(1));
+    42
+}
+
+#[test]
+fn sync_fn_without_return_value() {
+    sync_no_return();
+}
+
+#[test]
+fn sync_fn_with_return_value() {
+    assert_eq!(sync_with_return(), 42);
+}
+
+#[test]
+fn async_fn_without_return_value() {
+    block_on(async_no_return());
+}
+
+#[test]
+fn async_fn_with_return_value() {
+    assert_eq!(block_on(async_with_return()), 42);
+}
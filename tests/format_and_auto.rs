@@ -0,0 +1,40 @@
+//! Exercises the `auto` time unit and the `[...]` format mini-language on
+//! their own and together, since `auto` binds a `Duration` (only `Debug`,
+//! not `Display`) while the other units bind a plain integer.
+
+use std::{thread, time};
+
+use calculagraph::timer_println;
+
+#[timer_println(auto)]
+fn auto_default_format() -> u32 {
+    thread::sleep(time::Duration::from_millis(1));
+    42
+}
+
+#[timer_println(ms, "[fn] on [thread] took [duration][unit]")]
+fn template_format() -> u32 {
+    thread::sleep(time::Duration::from_millis(1));
+    42
+}
+
+#[timer_println(auto, "[fn] => [duration]")]
+fn auto_template_format() -> u32 {
+    thread::sleep(time::Duration::from_millis(1));
+    42
+}
+
+#[test]
+fn auto_unit_alone() {
+    assert_eq!(auto_default_format(), 42);
+}
+
+#[test]
+fn template_format_with_plain_unit() {
+    assert_eq!(template_format(), 42);
+}
+
+#[test]
+fn template_format_composes_with_auto_unit() {
+    assert_eq!(auto_template_format(), 42);
+}
@@ -0,0 +1,33 @@
+//! Annotating a whole `impl` block should time every method while leaving
+//! associated consts and types untouched.
+
+use std::{thread, time};
+
+use calculagraph::timer_log_debug;
+
+struct Service;
+
+#[timer_log_debug(us)]
+impl Service {
+    const VERSION: u32 = 1;
+
+    fn handle(&self, x: u32) -> u32 {
+        thread::sleep(time::Duration::from_millis(1));
+        x + 1
+    }
+
+    async fn handle_async(&self) -> u32 {
+        thread::sleep(time::Duration::from_millis(1));
+        7
+    }
+}
+
+#[test]
+fn instrumented_sync_method_keeps_return_value() {
+    assert_eq!(Service.handle(41), 42);
+}
+
+#[test]
+fn associated_const_is_left_untouched() {
+    assert_eq!(Service::VERSION, 1);
+}
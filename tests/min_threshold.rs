@@ -0,0 +1,41 @@
+//! Exercises the `min = Threshold` gate on `#[timer_println]`, including the
+//! `auto` unit, since `min` compares `elapsed()` against `Threshold` and `auto`
+//! binds a `Duration` rather than an integer.
+
+use std::{thread, time};
+
+use calculagraph::timer_println;
+
+#[timer_println(ms, min = 1000)]
+fn below_threshold() -> u32 {
+    42
+}
+
+#[timer_println(ms, min = 1)]
+fn at_or_above_threshold() -> u32 {
+    thread::sleep(time::Duration::from_millis(2));
+    42
+}
+
+#[timer_println(auto, min = 1)]
+fn auto_below_threshold() -> u32 {
+    42
+}
+
+#[timer_println(auto, min = 1)]
+fn auto_at_or_above_threshold() -> u32 {
+    thread::sleep(time::Duration::from_millis(2));
+    42
+}
+
+#[test]
+fn min_gate_does_not_affect_return_value() {
+    assert_eq!(below_threshold(), 42);
+    assert_eq!(at_or_above_threshold(), 42);
+}
+
+#[test]
+fn min_gate_composes_with_auto_unit() {
+    assert_eq!(auto_below_threshold(), 42);
+    assert_eq!(auto_at_or_above_threshold(), 42);
+}